@@ -1,30 +1,37 @@
+use std::sync::OnceLock;
+
+use rand::distributions::WeightedIndex;
 use rand::prelude::*;
 
+const ONSETS: [char; 9] = ['p', 't', 'k', 's', 'm', 'n', 'l', 'j', 'w'];
+const NUCLEI: [char; 5] = ['a', 'i', 'e', 'o', 'u'];
+
 fn main() {
     let mut rng = rand::rngs::SmallRng::from_entropy();
+    let mut name = String::new();
     for _ in 0..100 {
-        let name = name(&mut rng);
+        name_into(&mut name, &mut rng);
         if name.len() != 1 {
             println!("{}", name);
         }
     }
 }
 
-fn name(rng: &mut impl Rng) -> String {
+fn name_into(buf: &mut String, rng: &mut (impl Rng + ?Sized)) {
     let len = [1, 2, 2, 2, 2, 3, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 6]
         .choose(rng)
         .unwrap();
 
-    let mut name = String::new();
-    initial(&mut name, rng);
+    buf.clear();
+    // At most one onset, one nucleus and a coda per syllable, all ASCII.
+    buf.reserve(len * 3);
+    initial(buf, rng);
     for _ in 0..(len - 1) {
-        syllable(&mut name, rng);
+        syllable(buf, rng);
     }
-
-    name
 }
 
-fn initial(buf: &mut String, rng: &mut impl Rng) {
+fn initial(buf: &mut String, rng: &mut (impl Rng + ?Sized)) {
     if rng.gen_bool(0.25) {
         nucleus(buf, rng)
     } else {
@@ -32,43 +39,139 @@ fn initial(buf: &mut String, rng: &mut impl Rng) {
     }
 }
 
-fn syllable(buf: &mut String, rng: &mut impl Rng) {
-    let last = buf.chars().last();
-    let onset = ['p', 't', 'k', 's', 'm', 'n', 'l', 'j', 'w']
-        .choose_weighted(rng, |v| match v {
-            'p' => 61,
-            't' => 45,
-            'k' => 91,
-            's' => 64,
-            'm' if last != Some('n') => 50,
-            'n' if last != Some('n') => 32,
-            'l' => 83,
-            'j' => 35,
-            'w' => 34,
-            _ => 0,
-        })
-        .unwrap();
-    buf.push(*onset);
+fn syllable(buf: &mut String, rng: &mut (impl Rng + ?Sized)) {
+    let weights = onset_table(buf.chars().last());
+    buf.push(ONSETS[weights.sample(rng)]);
 
     nucleus(buf, rng);
 }
 
-fn nucleus(buf: &mut String, rng: &mut impl Rng) {
-    let last = buf.chars().last();
-    let nucleus = ['a', 'i', 'e', 'o', 'u']
-        .choose_weighted(rng, |v| match v {
-            'a' => 146,
-            'e' => 94,
-            'i' if last != Some('t') && last != Some('j') => 109,
-            'o' if last != Some('w') => 82,
-            'u' if last != Some('w') => 60,
-            _ => 0,
-        })
-        .unwrap();
+fn onset_table(last: Option<char>) -> &'static WeightedIndex<u32> {
+    static ONSET: OnceLock<WeightedIndex<u32>> = OnceLock::new();
+    static ONSET_NO_NASAL: OnceLock<WeightedIndex<u32>> = OnceLock::new();
 
-    buf.push(*nucleus);
+    match last {
+        Some('n') => ONSET_NO_NASAL.get_or_init(|| onset_weights(true)),
+        _ => ONSET.get_or_init(|| onset_weights(false)),
+    }
+}
+
+fn onset_weights(no_nasal: bool) -> WeightedIndex<u32> {
+    WeightedIndex::new(ONSETS.iter().map(|v| match v {
+        'p' => 61,
+        't' => 45,
+        'k' => 91,
+        's' => 64,
+        'm' if !no_nasal => 50,
+        'n' if !no_nasal => 32,
+        'l' => 83,
+        'j' => 35,
+        'w' => 34,
+        _ => 0,
+    }))
+    .unwrap()
+}
+
+fn nucleus(buf: &mut String, rng: &mut (impl Rng + ?Sized)) {
+    let weights = nucleus_table(buf.chars().last());
+    buf.push(NUCLEI[weights.sample(rng)]);
 
     if rng.gen_bool(0.06) {
         buf.push('n');
     }
 }
+
+fn nucleus_table(last: Option<char>) -> &'static WeightedIndex<u32> {
+    static NUCLEUS: OnceLock<WeightedIndex<u32>> = OnceLock::new();
+    static NUCLEUS_NO_I: OnceLock<WeightedIndex<u32>> = OnceLock::new();
+    static NUCLEUS_NO_O_U: OnceLock<WeightedIndex<u32>> = OnceLock::new();
+
+    match last {
+        Some('t' | 'j') => NUCLEUS_NO_I.get_or_init(|| nucleus_weights(true, false)),
+        Some('w') => NUCLEUS_NO_O_U.get_or_init(|| nucleus_weights(false, true)),
+        _ => NUCLEUS.get_or_init(|| nucleus_weights(false, false)),
+    }
+}
+
+fn nucleus_weights(no_i: bool, no_o_u: bool) -> WeightedIndex<u32> {
+    WeightedIndex::new(NUCLEI.iter().map(|v| match v {
+        'a' => 146,
+        'e' => 94,
+        'i' if !no_i => 109,
+        'o' if !no_o_u => 82,
+        'u' if !no_o_u => 60,
+        _ => 0,
+    }))
+    .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::{SmallRng, StdRng};
+
+    use super::*;
+
+    #[test]
+    fn name_into_clears_buffer() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let mut fresh_rng = SmallRng::seed_from_u64(1);
+
+        let mut buf = String::from("XYZ");
+        for _ in 0..2 {
+            name_into(&mut buf, &mut rng);
+
+            let mut fresh = String::new();
+            name_into(&mut fresh, &mut fresh_rng);
+            assert_eq!(buf, fresh);
+        }
+    }
+
+    #[test]
+    fn seeded_names_are_stable() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut buf = String::new();
+        let names: Vec<String> = (0..8)
+            .map(|_| {
+                name_into(&mut buf, &mut rng);
+                buf.clone()
+            })
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "inelu",
+                "ini",
+                "keku",
+                "ekikolalun",
+                "itu",
+                "te",
+                "tutanku",
+                "takojatan"
+            ],
+        );
+    }
+
+    #[test]
+    fn names_respect_phonotactics() {
+        let mut rng = SmallRng::seed_from_u64(7);
+        let mut buf = String::new();
+        for _ in 0..20000 {
+            name_into(&mut buf, &mut rng);
+            for illegal in ["ti", "ji", "wo", "wu", "nn", "nm"] {
+                assert!(!buf.contains(illegal), "{buf:?} contains {illegal:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn name_into_accepts_trait_object() {
+        let rng: &mut dyn RngCore = &mut SmallRng::seed_from_u64(3);
+        let mut buf = String::new();
+        name_into(&mut buf, rng);
+
+        assert!(!buf.is_empty());
+        assert!(buf
+            .chars()
+            .all(|c| ONSETS.contains(&c) || NUCLEI.contains(&c)));
+    }
+}